@@ -0,0 +1,121 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::view::ViewKind;
+
+/// Runtime configuration, deserialized from `~/.config/hello-pi-zero/config.toml`.
+///
+/// Every field has a sensible default so the program keeps working when no configuration file is
+/// present; `#[serde(default)]` fills in the ones the user did not set.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct Config {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub user_agent: String,
+    pub forecast_hours: usize,
+    #[serde(with = "duration_millis")]
+    pub refresh_interval: Duration,
+    pub plot_x_start: i32,
+    pub scale_y_minimum: i32,
+    pub scale_y_maximum: i32,
+    /// How many consecutive fetch errors may accumulate before the error budget counts as spent.
+    /// A hard error is only bubbled up once the budget is spent *and* no usable cached data
+    /// remains, so a fresh cache keeps the display alive regardless of this count. `None` never
+    /// considers the budget spent.
+    pub max_errors_in_row: Option<usize>,
+    /// How old cached forecast data may get before it is no longer served during an outage,
+    /// expressed in seconds in the TOML file. `None` never considers cached data too stale.
+    #[serde(with = "option_duration_secs")]
+    pub max_staleness: Option<Duration>,
+    /// Address to bind the embedded JSON API to, e.g. `0.0.0.0:8080`. `None` (the default) leaves
+    /// the server disabled so the device only drives the display.
+    pub server_address: Option<String>,
+    /// Display pages to cycle through, in order. Defaults to the temperature curve alone, matching
+    /// the original single-page behaviour.
+    pub views: Vec<ViewKind>,
+    /// How long each page stays on screen before the scheduler advances to the next one.
+    #[serde(with = "duration_secs")]
+    pub view_dwell: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            latitude: 49.0068,
+            longitude: 8.4036,
+            user_agent: "bloerg.net kontakt@bloerg.net".to_string(),
+            forecast_hours: 48,
+            refresh_interval: Duration::from_millis(1500),
+            plot_x_start: 16,
+            scale_y_minimum: 36,
+            scale_y_maximum: 20,
+            max_errors_in_row: Some(5),
+            max_staleness: Some(Duration::from_secs(3 * 3600)),
+            server_address: None,
+            views: vec![ViewKind::Temperature],
+            view_dwell: Duration::from_secs(8),
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from `~/.config/hello-pi-zero/config.toml`, falling back to the
+    /// defaults when the file does not exist.
+    pub fn load() -> Result<Self> {
+        let path = dirs::config_dir().map(|p| p.join("hello-pi-zero").join("config.toml"));
+
+        let path = match path {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Self::default()),
+        };
+
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Cannot read {:?}", path))?;
+
+        toml::from_str(&content).with_context(|| format!("Cannot parse {:?}", path))
+    }
+}
+
+/// Deserialize a `Duration` from a plain number of milliseconds in the TOML file.
+mod duration_millis {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let millis = u64::deserialize(deserializer)?;
+        Ok(Duration::from_millis(millis))
+    }
+}
+
+/// Deserialize a `Duration` from a plain number of seconds in the TOML file.
+mod duration_secs {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = u64::deserialize(deserializer)?;
+        Ok(Duration::from_secs(secs))
+    }
+}
+
+/// Deserialize an optional `Duration` from a plain number of seconds in the TOML file.
+mod option_duration_secs {
+    use serde::{Deserialize, Deserializer};
+    use std::time::Duration;
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let secs = Option::<u64>::deserialize(deserializer)?;
+        Ok(secs.map(Duration::from_secs))
+    }
+}
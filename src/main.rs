@@ -1,28 +1,278 @@
+mod config;
 mod met;
+mod onewire;
+mod view;
 
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, FixedOffset};
+use chrono::{DateTime, FixedOffset, Local, Utc};
 use embedded_graphics as gfx;
 use embedded_graphics::pixelcolor::BinaryColor;
 use embedded_graphics::prelude::*;
-use log::info;
+use log::{info, warn};
+use serde::Serialize;
 use sh1106::displaysize::DisplaySize;
 use sh1106::mode::GraphicsMode;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 use tokio::time;
 use tokio::try_join;
 
+/// The concrete SH1106 display type the render loop and all [`view::View`]s draw onto.
+type Display = GraphicsMode<sh1106::interface::I2cInterface<rppal::i2c::I2c>>;
+
+/// First backoff interval after a failed fetch; doubles with every consecutive error.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+/// Upper bound the exponential backoff is clamped to.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// 16x16 monochrome weather icons, drawn with `embedded_graphics` `ImageRaw`. Each icon is a row-
+/// major 1bpp bitmap (two bytes per row, MSB leftmost) selected from the met.no `symbol_code`.
+mod icons {
+    /// Side length of every icon in pixels; also the `ImageRaw` width.
+    pub const SIZE: u32 = 16;
+
+    const SUN: [u8; 32] = [
+        0b00000001, 0b10000000,
+        0b00010001, 0b10001000,
+        0b00001000, 0b00010000,
+        0b00000011, 0b11000000,
+        0b00000111, 0b11100000,
+        0b11000111, 0b11100011,
+        0b11000111, 0b11100011,
+        0b00000111, 0b11100000,
+        0b00000111, 0b11100000,
+        0b11000111, 0b11100011,
+        0b11000111, 0b11100011,
+        0b00000111, 0b11100000,
+        0b00000011, 0b11000000,
+        0b00001000, 0b00010000,
+        0b00010001, 0b10001000,
+        0b00000001, 0b10000000,
+    ];
+
+    const CLOUD: [u8; 32] = [
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00000011, 0b11000000,
+        0b00000110, 0b01100000,
+        0b00001100, 0b00110000,
+        0b00011000, 0b00011000,
+        0b00110000, 0b00001100,
+        0b01100000, 0b00000110,
+        0b01111111, 0b11111110,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+    ];
+
+    const RAIN: [u8; 32] = [
+        0b00000011, 0b11000000,
+        0b00000110, 0b01100000,
+        0b00001100, 0b00110000,
+        0b00011000, 0b00011000,
+        0b00110000, 0b00001100,
+        0b01111111, 0b11111110,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b00010010, 0b01001000,
+        0b00010010, 0b01001000,
+        0b00100100, 0b10010000,
+        0b00100100, 0b10010000,
+        0b01001001, 0b00100000,
+        0b01001001, 0b00100000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+    ];
+
+    const SNOW: [u8; 32] = [
+        0b00000011, 0b11000000,
+        0b00000110, 0b01100000,
+        0b00001100, 0b00110000,
+        0b00011000, 0b00011000,
+        0b00110000, 0b00001100,
+        0b01111111, 0b11111110,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b00010000, 0b00100000,
+        0b00111000, 0b01110000,
+        0b00010000, 0b00100000,
+        0b00000010, 0b00001000,
+        0b00000111, 0b00011100,
+        0b00000010, 0b00001000,
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+    ];
+
+    const FOG: [u8; 32] = [
+        0b00000000, 0b00000000,
+        0b00000000, 0b00000000,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b01111111, 0b11111110,
+        0b00000000, 0b00000000,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b01111111, 0b11111110,
+        0b00000000, 0b00000000,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+        0b01111111, 0b11111110,
+        0b00000000, 0b00000000,
+        0b00111111, 0b11111100,
+        0b00000000, 0b00000000,
+    ];
+
+    /// Map a met.no `symbol_code` to the bitmap of the closest matching condition, or `None` when
+    /// the code names something we do not draw an icon for.
+    pub fn bitmap(symbol_code: &str) -> Option<&'static [u8]> {
+        // Strip the `_day`/`_night`/`_polartwilight` variant suffix met.no appends.
+        let base = symbol_code.split('_').next().unwrap_or(symbol_code);
+
+        let icon = match base {
+            "clearsky" | "fair" => &SUN,
+            "partlycloudy" | "cloudy" => &CLOUD,
+            "rain" | "lightrain" | "heavyrain" | "rainshowers" | "lightrainshowers"
+            | "heavyrainshowers" | "sleet" | "sleetshowers" | "drizzle" => &RAIN,
+            "snow" | "lightsnow" | "heavysnow" | "snowshowers" | "lightsnowshowers"
+            | "heavysnowshowers" => &SNOW,
+            "fog" => &FOG,
+            _ => return None,
+        };
+
+        Some(icon)
+    }
+}
+
 /// Wrapper for tokio::time::sleep so we can use it in try_join!().
 async fn fallible_sleep(duration: time::Duration) -> Result<()> {
     time::sleep(duration).await;
     Ok(())
 }
 
+/// Read the 1-wire sensor if one was detected, fitting into `try_join!()` alongside the forecast.
+///
+/// A missing sensor or a transient read error degrades to `None` rather than aborting the render
+/// loop — not every Pi has the sensor wired up and a single failed read should not crash the
+/// display.
+async fn read_sensor(sensor: &Option<onewire::Ds18b20>) -> Result<Option<f32>> {
+    let sensor = match sensor {
+        Some(sensor) => sensor,
+        None => return Ok(None),
+    };
+
+    match sensor.read().await {
+        Ok(temperature) => Ok(Some(temperature)),
+        Err(err) => {
+            warn!("sensor read failed: {:#}", err);
+            Ok(None)
+        }
+    }
+}
+
+/// A slice of forecast data ready for plotting: one value per upcoming hour for each series.
+struct Forecast {
+    times: Vec<DateTime<Utc>>,
+    temperature: Vec<f32>,
+    precipitation: Vec<f32>,
+    wind: Vec<f32>,
+    symbol: Option<String>,
+}
+
 struct InnerState {
     client: met::Client,
+    forecast_hours: usize,
+    max_errors_in_row: Option<usize>,
+    max_staleness: Option<Duration>,
     expires: Option<DateTime<FixedOffset>>,
     last_response: Option<met::Response>,
+    last_success: Option<DateTime<Local>>,
+    errors_in_row: usize,
+    backoff_until: Option<DateTime<Local>>,
+    /// Latest DS18B20 reading, exposed over the JSON API; `None` until the first successful read.
+    last_measured: Option<f32>,
+}
+
+impl InnerState {
+    /// Build the plottable series from a response for the configured window.
+    fn series(&self, response: &met::Response, now: DateTime<Local>) -> Result<Forecast> {
+        Ok(Forecast {
+            times: response.times_next_n_hours(now, self.forecast_hours)?,
+            temperature: response.next_n_hours(now, self.forecast_hours)?,
+            precipitation: response.precipitation_next_n_hours(now, self.forecast_hours)?,
+            wind: response.wind_next_n_hours(now, self.forecast_hours)?,
+            symbol: response.current_symbol(now).map(str::to_owned),
+        })
+    }
+
+    /// Record a successful fetch: cache the response, stamp the expiry, and reset the error budget.
+    fn on_success(
+        &mut self,
+        now: DateTime<Local>,
+        expires: DateTime<FixedOffset>,
+        response: met::Response,
+    ) -> Result<Forecast> {
+        let data = self.series(&response, now)?;
+
+        self.expires = Some(expires);
+        self.last_response = Some(response);
+        self.last_success = Some(now);
+        self.errors_in_row = 0;
+        self.backoff_until = None;
+
+        Ok(data)
+    }
+
+    /// The last good forecast, as long as it is present and not older than `max_staleness`.
+    fn cached(&self, now: DateTime<Local>) -> Option<Forecast> {
+        let response = self.last_response.as_ref()?;
+
+        if let (Some(max_staleness), Some(last_success)) = (self.max_staleness, self.last_success) {
+            let age = now.signed_duration_since(last_success);
+            if age > chrono::Duration::from_std(max_staleness).ok()? {
+                return None;
+            }
+        }
+
+        self.series(response, now).ok()
+    }
+
+    /// Record a failed fetch, schedule exponential backoff, and serve cached data while it is
+    /// still usable, whether or not the error budget is spent.
+    ///
+    /// Returns `Ok(Some(..))` while a usable cache remains, `Ok(None)` when the budget still holds
+    /// but nothing is cached yet (the loop skips the frame and retries after the backoff), and
+    /// `Err` only once the budget is spent *and* no usable cache remains — so a transient blip,
+    /// including at cold start before the network is up, never crashes the render loop.
+    fn on_error(&mut self, now: DateTime<Local>, err: anyhow::Error) -> Result<Option<Forecast>> {
+        self.errors_in_row += 1;
+
+        let shift = (self.errors_in_row - 1).min(u32::MAX as usize) as u32;
+        let backoff = INITIAL_BACKOFF
+            .checked_mul(2u32.saturating_pow(shift))
+            .unwrap_or(MAX_BACKOFF)
+            .min(MAX_BACKOFF);
+        self.backoff_until = chrono::Duration::from_std(backoff).ok().map(|d| now + d);
+
+        warn!(
+            "forecast fetch failed ({} in a row): {:#}",
+            self.errors_in_row, err
+        );
+
+        let budget_exhausted = self
+            .max_errors_in_row
+            .map_or(false, |max| self.errors_in_row > max);
+
+        match self.cached(now) {
+            Some(data) => Ok(Some(data)),
+            None if budget_exhausted => Err(err),
+            None => Ok(None),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -31,44 +281,127 @@ struct State {
 }
 
 impl State {
-    /// Return forecast data if not stale yet.
-    async fn forecast(&self) -> Result<Vec<f32>> {
-        let mut state = self.inner.write().await;
+    /// Return forecast data to draw, or `None` when a fetch failed but the error budget still
+    /// holds and nothing is cached yet — the render loop skips that frame and retries on the
+    /// backoff schedule. The network request runs without any lock held so a slow upstream cannot
+    /// stall the render loop or the JSON API handlers.
+    async fn forecast(&self) -> Result<Option<Forecast>> {
         let now = chrono::Local::now();
 
-        // Return early if we should not update the forecast data
-        if let Some(expires) = state.expires {
-            if now < expires {
-                if let Some(response) = &state.last_response {
-                    return Ok(response.next_n_hours(now, 48)?);
+        // Under a read lock, decide whether we can serve cached data without fetching.
+        {
+            let state = self.inner.read().await;
+
+            if let Some(expires) = state.expires {
+                if now < expires {
+                    if let Some(response) = &state.last_response {
+                        return Ok(Some(state.series(response, now)?));
+                    }
+                }
+            }
+
+            // Respect the backoff window after a failed fetch: keep serving cached data rather than
+            // hammering the API on every loop iteration.
+            if let Some(until) = state.backoff_until {
+                if now < until {
+                    return Ok(state.cached(now));
                 }
             }
         }
 
         info!("fetching forecast data");
 
-        let response = state.client.get().await?;
+        // Fetch outside any lock so a hung upstream can't freeze the render loop or the API.
+        let client = self.inner.read().await.client.clone();
+        let result = client.fetch().await;
 
-        let value = response
-            .headers()
-            .get("expires")
-            .ok_or_else(|| anyhow!("No expires in the header map"))?;
+        let mut state = self.inner.write().await;
+        match result {
+            Ok((expires, response)) => state.on_success(now, expires, response).map(Some),
+            Err(err) => state.on_error(now, err),
+        }
+    }
 
-        let expires = chrono::DateTime::parse_from_rfc2822(value.to_str()?)?;
-        state.expires = Some(expires);
+    /// Snapshot of the cached forecast for the JSON API, paired by hour.
+    async fn forecast_payload(&self) -> Result<ForecastPayload> {
+        let state = self.inner.read().await;
+        let now = chrono::Local::now();
+        let response = state
+            .last_response
+            .as_ref()
+            .ok_or_else(|| anyhow!("no forecast cached yet"))?;
 
-        let response: met::Response = response.json().await?;
-        let data = response.next_n_hours(now, 48)?;
-        state.last_response = Some(response);
+        Ok(ForecastPayload {
+            times: response.times_next_n_hours(now, state.forecast_hours)?,
+            temperature: response.next_n_hours(now, state.forecast_hours)?,
+            precipitation: response.precipitation_next_n_hours(now, state.forecast_hours)?,
+            wind: response.wind_next_n_hours(now, state.forecast_hours)?,
+        })
+    }
 
-        Ok(data)
+    /// Latest sensor reading exposed over the JSON API.
+    async fn sensor_reading(&self) -> SensorPayload {
+        SensorPayload {
+            temperature: self.inner.read().await.last_measured,
+        }
     }
 }
 
+/// Forecast slice returned by `GET /forecast`, one entry per upcoming hour across the series.
+#[derive(Serialize)]
+struct ForecastPayload {
+    times: Vec<DateTime<Utc>>,
+    temperature: Vec<f32>,
+    precipitation: Vec<f32>,
+    wind: Vec<f32>,
+}
+
+/// Latest sensor reading returned by `GET /sensor`.
+#[derive(Serialize)]
+struct SensorPayload {
+    temperature: Option<f32>,
+}
+
+async fn forecast_endpoint(
+    axum::extract::State(state): axum::extract::State<State>,
+) -> std::result::Result<axum::Json<ForecastPayload>, (axum::http::StatusCode, String)> {
+    state
+        .forecast_payload()
+        .await
+        .map(axum::Json)
+        .map_err(|err| (axum::http::StatusCode::SERVICE_UNAVAILABLE, format!("{:#}", err)))
+}
+
+async fn sensor_endpoint(
+    axum::extract::State(state): axum::extract::State<State>,
+) -> axum::Json<SensorPayload> {
+    axum::Json(state.sensor_reading().await)
+}
+
+/// Run the embedded JSON API, serving the cached forecast and the latest sensor reading so the
+/// device doubles as a tiny weather API on the LAN.
+async fn serve(address: String, state: State) -> Result<()> {
+    let app = axum::Router::new()
+        .route("/forecast", axum::routing::get(forecast_endpoint))
+        .route("/sensor", axum::routing::get(sensor_endpoint))
+        .with_state(state);
+
+    info!("serving JSON API on {}", address);
+
+    let listener = tokio::net::TcpListener::bind(&address)
+        .await
+        .with_context(|| format!("Cannot bind {}", address))?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     env_logger::init();
 
+    let config = config::Config::load()?;
+
     let i2c = rppal::i2c::I2c::new().context("Unable to create I2c object")?;
 
     let mut display: GraphicsMode<_> = sh1106::builder::Builder::new()
@@ -80,54 +413,111 @@ async fn main() -> Result<()> {
     display.flush().unwrap();
 
     let inner = Arc::new(RwLock::new(InnerState {
-        client: met::Client::new()?,
+        client: met::Client::new(&config)?,
+        forecast_hours: config.forecast_hours,
+        max_errors_in_row: config.max_errors_in_row,
+        max_staleness: config.max_staleness,
         expires: None,
         last_response: None,
+        last_success: None,
+        errors_in_row: 0,
+        backoff_until: None,
+        last_measured: None,
     }));
 
     let state = State { inner };
+
+    // Not every Pi has the DS18B20 wired up; degrade gracefully when it is absent.
+    let sensor = match onewire::Ds18b20::new() {
+        Ok(sensor) => Some(sensor),
+        Err(err) => {
+            warn!("no temperature sensor available: {:#}", err);
+            None
+        }
+    };
+
     let text_style = gfx::mono_font::MonoTextStyle::new(&profont::PROFONT_7_POINT, BinaryColor::On);
     let line_style = gfx::primitives::PrimitiveStyleBuilder::new()
         .stroke_color(BinaryColor::On)
         .stroke_width(1)
         .build();
-    let sleep_duration = time::Duration::from_millis(1500);
-
-    let plot_x_start = 16;
-    let scale_y_minimum = 36;
-    let scale_y_maximum = 20;
-    let scale_height = (scale_y_minimum - scale_y_maximum) as f32;
-
-    loop {
-        let (datapoints, _) = try_join!(state.forecast(), fallible_sleep(sleep_duration))?;
-
-        let minimum = datapoints.iter().fold(f32::INFINITY, |a, &b| a.min(b));
-        let maximum = datapoints.iter().fold(-f32::INFINITY, |a, &b| a.max(b));
-        let range = maximum - minimum;
-        let minimum_temp = format!("{:>2.0}°", minimum);
-        let maximum_temp = format!("{:>2.0}°", maximum);
-
-        // Would be great to update the display in a future as well but it's a pain to store it
-        // in the `State` struct ...
-        display.clear();
-
-        // Draw scale mins and maxs
-        gfx::text::Text::new(&maximum_temp, Point::new(0, scale_y_maximum), text_style)
-            .draw(&mut display)?;
-        gfx::text::Text::new(&minimum_temp, Point::new(0, scale_y_minimum), text_style)
-            .draw(&mut display)?;
-
-        // Draw pin plot
-        for (index, temperature) in datapoints.iter().enumerate() {
-            let x = (index * 2) as i32;
-            let height = (((temperature - minimum) / range) * scale_height) as i32;
-            let start = Point::new(plot_x_start + x, scale_y_minimum);
-            let end = Point::new(plot_x_start + x, scale_y_minimum - height);
-            gfx::primitives::Line::new(start, end)
-                .into_styled(line_style)
-                .draw(&mut display)?;
+    let fill_style = gfx::primitives::PrimitiveStyleBuilder::new()
+        .fill_color(BinaryColor::On)
+        .build();
+    let sleep_duration = config.refresh_interval;
+
+    let geometry = view::Geometry {
+        plot_x_start: config.plot_x_start,
+        scale_y_minimum: config.scale_y_minimum,
+        scale_y_maximum: config.scale_y_maximum,
+    };
+    let views = config.views.clone();
+    let view_dwell = config.view_dwell;
+
+    let server_address = config.server_address.clone();
+    let state_for_server = state.clone();
+
+    // The render loop owns the display and drives it forever; it runs concurrently with the
+    // optional JSON API in the `try_join!` below. The scheduler cycles through the enabled views
+    // on the configured dwell interval so the screen rotates between pages.
+    let render = async move {
+        let mut current = 0usize;
+        let mut last_switch = time::Instant::now();
+
+        loop {
+            let (forecast, measured, _) = try_join!(
+                state.forecast(),
+                read_sensor(&sensor),
+                fallible_sleep(sleep_duration)
+            )?;
+            // Retain the last good reading: a transient read failure yields `None` here and must
+            // not clobber a previously measured value exposed over `GET /sensor`.
+            if let Some(measured) = measured {
+                state.inner.write().await.last_measured = Some(measured);
+            }
+
+            // No data to draw yet (fetch failed while the budget holds and nothing is cached):
+            // keep the current frame; `forecast()` fetches again once the backoff has passed.
+            let forecast = match forecast {
+                Some(forecast) => forecast,
+                None => continue,
+            };
+
+            if !views.is_empty() && last_switch.elapsed() >= view_dwell {
+                current = (current + 1) % views.len();
+                last_switch = time::Instant::now();
+            }
+
+            let data = view::RenderData {
+                forecast: &forecast,
+                measured,
+                geometry: &geometry,
+                text_style,
+                line_style,
+                fill_style,
+            };
+
+            // Would be great to update the display in a future as well but it's a pain to store it
+            // in the `State` struct ...
+            display.clear();
+            if let Some(kind) = views.get(current) {
+                kind.view().render(&mut display, &data)?;
+            }
+            display.flush().unwrap();
         }
 
-        display.flush().unwrap();
+        // The render loop never terminates on its own; this satisfies the `try_join!` return type.
+        #[allow(unreachable_code)]
+        Ok::<(), anyhow::Error>(())
+    };
+
+    // Run the display loop alongside the JSON API when one is configured; otherwise just render.
+    match server_address {
+        Some(address) => {
+            try_join!(render, serve(address, state_for_server))?;
+        }
+        None => render.await?,
     }
+
+    Ok(())
 }
@@ -1,13 +1,21 @@
-use anyhow::Result;
-use chrono::{DateTime, Utc};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, FixedOffset, Utc};
 use serde::Deserialize;
+use std::time::Duration;
 
-const FORECAST_URL: &'static str =
-    "https://api.met.no/weatherapi/locationforecast/2.0/compact?lat=49.0068&lon=8.4036";
+use crate::config::Config;
+
+const FORECAST_BASE_URL: &'static str =
+    "https://api.met.no/weatherapi/locationforecast/2.0/compact";
+
+/// Upper bound on a single forecast request, so a hung upstream fails the fetch (feeding the error
+/// budget) instead of blocking the caller forever.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(Deserialize, Debug)]
 pub struct Details {
     pub air_temperature: f32,
+    pub wind_speed: f32,
 }
 
 #[derive(Deserialize, Debug)]
@@ -15,9 +23,27 @@ pub struct Instant {
     pub details: Details,
 }
 
+#[derive(Deserialize, Debug)]
+pub struct PeriodDetails {
+    pub precipitation_amount: f32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Summary {
+    pub symbol_code: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Period {
+    pub summary: Summary,
+    pub details: PeriodDetails,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct Data {
     pub instant: Instant,
+    // The final timeseries entries drop the hourly block, so keep it optional.
+    pub next_1_hours: Option<Period>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -47,21 +73,114 @@ impl Response {
             .map(|e| e.data.instant.details.air_temperature)
             .collect::<Vec<_>>())
     }
+
+    pub fn times_next_n_hours(
+        &self,
+        dt: chrono::DateTime<chrono::Local>,
+        n: usize,
+    ) -> Result<Vec<DateTime<Utc>>> {
+        Ok(self
+            .properties
+            .timeseries
+            .iter()
+            .filter(|e| e.time > dt)
+            .take(n)
+            .map(|e| e.time)
+            .collect::<Vec<_>>())
+    }
+
+    pub fn precipitation_next_n_hours(
+        &self,
+        dt: chrono::DateTime<chrono::Local>,
+        n: usize,
+    ) -> Result<Vec<f32>> {
+        Ok(self
+            .properties
+            .timeseries
+            .iter()
+            .filter(|e| e.time > dt)
+            .take(n)
+            .map(|e| {
+                e.data
+                    .next_1_hours
+                    .as_ref()
+                    .map(|p| p.details.precipitation_amount)
+                    .unwrap_or(0.0)
+            })
+            .collect::<Vec<_>>())
+    }
+
+    pub fn wind_next_n_hours(
+        &self,
+        dt: chrono::DateTime<chrono::Local>,
+        n: usize,
+    ) -> Result<Vec<f32>> {
+        Ok(self
+            .properties
+            .timeseries
+            .iter()
+            .filter(|e| e.time > dt)
+            .take(n)
+            .map(|e| e.data.instant.details.wind_speed)
+            .collect::<Vec<_>>())
+    }
+
+    /// Symbol code (e.g. `partlycloudy_day`) of the nearest upcoming hour, if one carries an
+    /// `next_1_hours` block with a summary.
+    pub fn current_symbol(&self, dt: chrono::DateTime<chrono::Local>) -> Option<&str> {
+        self.properties
+            .timeseries
+            .iter()
+            .filter(|e| e.time > dt)
+            .find_map(|e| {
+                e.data
+                    .next_1_hours
+                    .as_ref()
+                    .map(|p| p.summary.symbol_code.as_str())
+            })
+    }
 }
 
+#[derive(Clone)]
 pub struct Client {
     client: reqwest::Client,
+    url: String,
 }
 
 impl Client {
-    pub fn new() -> Result<Self> {
+    pub fn new(config: &Config) -> Result<Self> {
         let client = reqwest::ClientBuilder::new()
-            .user_agent("bloerg.net kontakt@bloerg.net")
+            .user_agent(config.user_agent.clone())
+            .timeout(REQUEST_TIMEOUT)
             .build()?;
-        Ok(Self { client })
+
+        let url = format!(
+            "{}?lat={}&lon={}",
+            FORECAST_BASE_URL, config.latitude, config.longitude
+        );
+
+        Ok(Self { client, url })
     }
 
     pub async fn get(&self) -> Result<reqwest::Response> {
-        Ok(self.client.get(FORECAST_URL).send().await?)
+        Ok(self.client.get(&self.url).send().await?)
+    }
+
+    /// Fetch and parse a fresh forecast, returning it together with the `expires` timestamp from
+    /// the response headers. Performs all network and JSON work so the caller can run it without
+    /// holding any lock.
+    pub async fn fetch(&self) -> Result<(DateTime<FixedOffset>, Response)> {
+        let response = self.get().await?;
+
+        let value = response
+            .headers()
+            .get("expires")
+            .ok_or_else(|| anyhow!("No expires in the header map"))?;
+
+        let expires = DateTime::parse_from_rfc2822(value.to_str()?)?;
+
+        let response: Response = response.json().await?;
+
+        Ok((expires, response))
     }
 }
@@ -0,0 +1,231 @@
+//! Display pages rendered onto the SH1106 screen.
+//!
+//! A [`View`] knows how to draw one page from the shared [`RenderData`]; the render loop in `main`
+//! cycles through the enabled views (see [`ViewKind`]) on a configurable dwell interval so the
+//! 128x64 screen rotates between pages instead of always showing the same temperature plot.
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use embedded_graphics as gfx;
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use serde::Deserialize;
+
+use crate::{icons, Display, Forecast};
+
+/// Baseline of the precipitation bars drawn along the bottom edge.
+const PRECIP_Y_BASE: i32 = 63;
+/// Height of the precipitation bars on the temperature page.
+const PRECIP_HEIGHT: i32 = 12;
+
+/// Plot geometry, threaded through from the configuration.
+pub struct Geometry {
+    pub plot_x_start: i32,
+    pub scale_y_minimum: i32,
+    pub scale_y_maximum: i32,
+}
+
+/// Everything a [`View`] needs to draw a page: the current forecast, the latest sensor reading,
+/// the plot geometry, and the shared drawing styles.
+pub struct RenderData<'a> {
+    pub forecast: &'a Forecast,
+    pub measured: Option<f32>,
+    pub geometry: &'a Geometry,
+    pub text_style: gfx::mono_font::MonoTextStyle<'a, BinaryColor>,
+    pub line_style: gfx::primitives::PrimitiveStyle<BinaryColor>,
+    pub fill_style: gfx::primitives::PrimitiveStyle<BinaryColor>,
+}
+
+/// A single display page. The render loop clears the display before calling `render`.
+pub trait View {
+    fn render(&self, display: &mut Display, data: &RenderData) -> Result<()>;
+}
+
+/// Which pages are enabled and in what order, chosen in the configuration file.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewKind {
+    Temperature,
+    Precipitation,
+    Conditions,
+    NextHours,
+}
+
+impl ViewKind {
+    /// The [`View`] implementation backing this page.
+    pub fn view(self) -> &'static dyn View {
+        match self {
+            ViewKind::Temperature => &TemperatureCurve,
+            ViewKind::Precipitation => &PrecipitationBars,
+            ViewKind::Conditions => &Conditions,
+            ViewKind::NextHours => &NextHours,
+        }
+    }
+}
+
+/// Temperature as a pin plot with min/max scale labels and a wind-speed overlay.
+struct TemperatureCurve;
+
+impl View for TemperatureCurve {
+    fn render(&self, display: &mut Display, data: &RenderData) -> Result<()> {
+        let temperature = &data.forecast.temperature;
+        let geometry = data.geometry;
+        let scale_height = (geometry.scale_y_minimum - geometry.scale_y_maximum) as f32;
+
+        let minimum = temperature.iter().fold(f32::INFINITY, |a, &b| a.min(b));
+        let maximum = temperature.iter().fold(-f32::INFINITY, |a, &b| a.max(b));
+        let range = maximum - minimum;
+
+        let minimum_temp = format!("{:>2.0}°", minimum);
+        let maximum_temp = format!("{:>2.0}°", maximum);
+
+        // Draw scale mins and maxs
+        gfx::text::Text::new(
+            &maximum_temp,
+            Point::new(0, geometry.scale_y_maximum),
+            data.text_style,
+        )
+        .draw(display)?;
+        gfx::text::Text::new(
+            &minimum_temp,
+            Point::new(0, geometry.scale_y_minimum),
+            data.text_style,
+        )
+        .draw(display)?;
+
+        // Draw pin plot
+        for (index, temperature) in temperature.iter().enumerate() {
+            let x = (index * 2) as i32;
+            let height = (((temperature - minimum) / range) * scale_height) as i32;
+            let start = Point::new(geometry.plot_x_start + x, geometry.scale_y_minimum);
+            let end = Point::new(geometry.plot_x_start + x, geometry.scale_y_minimum - height);
+            gfx::primitives::Line::new(start, end)
+                .into_styled(data.line_style)
+                .draw(display)?;
+        }
+
+        // Draw precipitation as filled bars along the bottom.
+        let precipitation = &data.forecast.precipitation;
+        let precip_max = precipitation.iter().fold(0.0f32, |a, &b| a.max(b));
+        if precip_max > 0.0 {
+            for (index, amount) in precipitation.iter().enumerate() {
+                let height = ((amount / precip_max) * PRECIP_HEIGHT as f32) as i32;
+                if height <= 0 {
+                    continue;
+                }
+                let x = geometry.plot_x_start + (index * 2) as i32;
+                gfx::primitives::Rectangle::new(
+                    Point::new(x, PRECIP_Y_BASE - height),
+                    Size::new(1, height as u32),
+                )
+                .into_styled(data.fill_style)
+                .draw(display)?;
+            }
+        }
+
+        // Overlay wind speed as a second line in the temperature band.
+        let wind = &data.forecast.wind;
+        let wind_max = wind.iter().fold(0.0f32, |a, &b| a.max(b));
+        if wind_max > 0.0 {
+            let mut previous: Option<Point> = None;
+            for (index, speed) in wind.iter().enumerate() {
+                let height = ((speed / wind_max) * scale_height) as i32;
+                let x = geometry.plot_x_start + (index * 2) as i32;
+                let point = Point::new(x, geometry.scale_y_minimum - height);
+                if let Some(previous) = previous {
+                    gfx::primitives::Line::new(previous, point)
+                        .into_styled(data.line_style)
+                        .draw(display)?;
+                }
+                previous = Some(point);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Precipitation amounts as full-height filled bars across the screen, with the peak labelled.
+struct PrecipitationBars;
+
+impl View for PrecipitationBars {
+    fn render(&self, display: &mut Display, data: &RenderData) -> Result<()> {
+        let precipitation = &data.forecast.precipitation;
+        let precip_max = precipitation.iter().fold(0.0f32, |a, &b| a.max(b));
+
+        let label = format!("rain {:.1}mm", precip_max);
+        gfx::text::Text::new(&label, Point::new(0, 8), data.text_style).draw(display)?;
+
+        if precip_max > 0.0 {
+            let bar_height = (PRECIP_Y_BASE - 12) as f32;
+            for (index, amount) in precipitation.iter().enumerate() {
+                let height = ((amount / precip_max) * bar_height) as i32;
+                if height <= 0 {
+                    continue;
+                }
+                let x = (index * 2) as i32;
+                gfx::primitives::Rectangle::new(
+                    Point::new(x, PRECIP_Y_BASE - height),
+                    Size::new(1, height as u32),
+                )
+                .into_styled(data.fill_style)
+                .draw(display)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Current conditions: the weather symbol, the upcoming temperature, and the sensor reading.
+struct Conditions;
+
+impl View for Conditions {
+    fn render(&self, display: &mut Display, data: &RenderData) -> Result<()> {
+        if let Some(bitmap) = data.forecast.symbol.as_deref().and_then(icons::bitmap) {
+            let raw = gfx::image::ImageRaw::<BinaryColor>::new(bitmap, icons::SIZE);
+            gfx::image::Image::new(&raw, Point::new(0, 0)).draw(display)?;
+        }
+
+        if let Some(first) = data.forecast.temperature.first() {
+            let text = format!("{:.0}°", first);
+            gfx::text::Text::new(&text, Point::new(24, 12), data.text_style).draw(display)?;
+        }
+
+        if let Some(measured) = data.measured {
+            let text = match data.forecast.temperature.first() {
+                Some(first) => format!("indoor {:.1}° {:+.1}", measured, measured - first),
+                None => format!("indoor {:.1}°", measured),
+            };
+            gfx::text::Text::new(&text, Point::new(0, 40), data.text_style).draw(display)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A text table of the clock and the next upcoming hours with their temperatures.
+struct NextHours;
+
+impl View for NextHours {
+    fn render(&self, display: &mut Display, data: &RenderData) -> Result<()> {
+        let clock = Local::now().format("%H:%M").to_string();
+        gfx::text::Text::new(&clock, Point::new(0, 8), data.text_style).draw(display)?;
+
+        let forecast = data.forecast;
+        for (row, (time, temperature)) in forecast
+            .times
+            .iter()
+            .zip(forecast.temperature.iter())
+            .take(6)
+            .enumerate()
+        {
+            let local: DateTime<Local> = time.with_timezone(&Local);
+            let text = format!("{}  {:>2.0}°", local.format("%H:%M"), temperature);
+            let y = 20 + row as i32 * 8;
+            gfx::text::Text::new(&text, Point::new(0, y), data.text_style).draw(display)?;
+        }
+
+        Ok(())
+    }
+}